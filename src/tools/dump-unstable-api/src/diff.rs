@@ -0,0 +1,178 @@
+//! Diffs the unstable feature surface recorded in two [`FeatureReport`] sets, e.g. from two
+//! rustdoc JSON snapshots of the standard library taken at different points in time.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::report::FeatureReport;
+
+/// What happened to a single item's path between the old and new snapshot.
+#[derive(Serialize)]
+pub enum Change {
+    /// Present in the new snapshot, absent from the old one.
+    Added,
+    /// Present in the old snapshot, absent from the new one.
+    Removed,
+    /// Present in both, but now gated behind this feature instead of the named, previous one.
+    MovedFrom(String),
+    /// Present in both, but no longer gated behind this feature; now behind the named one.
+    MovedTo(String),
+}
+
+#[derive(Serialize)]
+pub struct ItemChange {
+    pub path: String,
+    pub change: Change,
+}
+
+/// The changes to a single feature's gated items between two snapshots.
+#[derive(Serialize)]
+pub struct FeatureDelta {
+    pub feature: String,
+    pub changes: Vec<ItemChange>,
+    /// `new item count - old item count`, including items that moved in or out.
+    pub item_count_delta: isize,
+}
+
+/// Flattens a set of reports into `fully_qualified_path -> feature` and `feature -> item count`.
+fn flatten(reports: &[FeatureReport]) -> (HashMap<&str, &str>, HashMap<&str, usize>) {
+    let mut path_to_feature = HashMap::new();
+    let mut counts = HashMap::new();
+    for report in reports {
+        counts.insert(report.feature.as_str(), report.items.len());
+        for item in &report.items {
+            path_to_feature.insert(item.path.as_str(), report.feature.as_str());
+        }
+    }
+    (path_to_feature, counts)
+}
+
+/// Computes, per feature, which items were added, removed, or moved in from/out to another
+/// feature between `old` and `new`.
+pub fn diff_reports(old: &[FeatureReport], new: &[FeatureReport]) -> Vec<FeatureDelta> {
+    let (old_paths, old_counts) = flatten(old);
+    let (new_paths, new_counts) = flatten(new);
+
+    let mut changes_by_feature: HashMap<&str, Vec<ItemChange>> = HashMap::new();
+    let all_paths = old_paths.keys().chain(new_paths.keys()).copied().collect::<std::collections::HashSet<_>>();
+
+    for path in all_paths {
+        match (old_paths.get(path), new_paths.get(path)) {
+            (None, Some(&feature)) => changes_by_feature.entry(feature).or_default().push(
+                ItemChange { path: path.to_owned(), change: Change::Added },
+            ),
+            (Some(&feature), None) => changes_by_feature.entry(feature).or_default().push(
+                ItemChange { path: path.to_owned(), change: Change::Removed },
+            ),
+            (Some(&old_feature), Some(&new_feature)) if old_feature != new_feature => {
+                changes_by_feature.entry(old_feature).or_default().push(ItemChange {
+                    path: path.to_owned(),
+                    change: Change::MovedTo(new_feature.to_owned()),
+                });
+                changes_by_feature.entry(new_feature).or_default().push(ItemChange {
+                    path: path.to_owned(),
+                    change: Change::MovedFrom(old_feature.to_owned()),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let mut features: Vec<&str> =
+        old_counts.keys().chain(new_counts.keys()).copied().collect::<std::collections::HashSet<_>>().into_iter().collect();
+    features.sort_unstable();
+
+    features
+        .into_iter()
+        .map(|feature| {
+            let old_count = *old_counts.get(feature).unwrap_or(&0) as isize;
+            let new_count = *new_counts.get(feature).unwrap_or(&0) as isize;
+            let mut changes = changes_by_feature.remove(feature).unwrap_or_default();
+            // `all_paths` is collected through a `HashSet`, so `changes` isn't in a stable order
+            // across runs on byte-identical input; sort so the diff is itself diffable.
+            changes.sort_by(|a, b| a.path.cmp(&b.path));
+            FeatureDelta { feature: feature.to_owned(), changes, item_count_delta: new_count - old_count }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::ItemEntry;
+
+    fn item(path: &str) -> ItemEntry {
+        ItemEntry {
+            path: path.to_owned(),
+            kind: "function".to_owned(),
+            issue: None,
+            reason: None,
+            since: None,
+            deprecated: None,
+            stability: "Unstable".to_owned(),
+            tree: Vec::new(),
+        }
+    }
+
+    fn report(feature: &str, items: Vec<ItemEntry>) -> FeatureReport {
+        FeatureReport { feature: feature.to_owned(), items }
+    }
+
+    fn delta<'a>(deltas: &'a [FeatureDelta], feature: &str) -> &'a FeatureDelta {
+        deltas.iter().find(|d| d.feature == feature).expect("feature not present in diff")
+    }
+
+    #[test]
+    fn detects_added_and_removed_items() {
+        let old = vec![report("foo", vec![item("a::b"), item("a::c")])];
+        let new = vec![report("foo", vec![item("a::b"), item("a::d")])];
+
+        let deltas = diff_reports(&old, &new);
+        let foo = delta(&deltas, "foo");
+        assert_eq!(foo.item_count_delta, 0);
+        assert_eq!(foo.changes.len(), 2);
+        assert!(
+            foo.changes.iter().any(|c| c.path == "a::c" && matches!(c.change, Change::Removed))
+        );
+        assert!(foo.changes.iter().any(|c| c.path == "a::d" && matches!(c.change, Change::Added)));
+    }
+
+    #[test]
+    fn detects_item_moved_between_features() {
+        let old = vec![report("foo", vec![item("a::b")]), report("bar", vec![])];
+        let new = vec![report("foo", vec![]), report("bar", vec![item("a::b")])];
+
+        let deltas = diff_reports(&old, &new);
+
+        let foo = delta(&deltas, "foo");
+        assert_eq!(foo.changes.len(), 1);
+        assert!(matches!(&foo.changes[0].change, Change::MovedTo(to) if to == "bar"));
+
+        let bar = delta(&deltas, "bar");
+        assert_eq!(bar.changes.len(), 1);
+        assert!(matches!(&bar.changes[0].change, Change::MovedFrom(from) if from == "foo"));
+    }
+
+    #[test]
+    fn unchanged_items_produce_no_changes() {
+        let old = vec![report("foo", vec![item("a::b")])];
+        let new = vec![report("foo", vec![item("a::b")])];
+
+        let deltas = diff_reports(&old, &new);
+        let foo = delta(&deltas, "foo");
+        assert!(foo.changes.is_empty());
+        assert_eq!(foo.item_count_delta, 0);
+    }
+
+    #[test]
+    fn changes_are_sorted_by_path() {
+        let old = vec![report("foo", vec![])];
+        let new = vec![report("foo", vec![item("z"), item("a"), item("m")])];
+
+        let deltas = diff_reports(&old, &new);
+        let foo = delta(&deltas, "foo");
+        let paths: Vec<&str> = foo.changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["a", "m", "z"]);
+    }
+}
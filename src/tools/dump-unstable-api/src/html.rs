@@ -0,0 +1,50 @@
+//! Renders a [`FeatureReport`] list as a single, browsable HTML page.
+
+use build_html::{Container, ContainerType, Html, HtmlContainer, HtmlPage};
+
+use crate::report::FeatureReport;
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Builds the `<li>` entries for a single feature's gated items.
+fn items_list(report: &FeatureReport) -> Container {
+    report.items.iter().fold(Container::new(ContainerType::UnorderedList), |list, item| {
+        let issue = item.issue.as_deref().map(|i| format!(", issue #{i}")).unwrap_or_default();
+        let reason = item.reason.as_deref().map(|r| format!(", reason: {r}")).unwrap_or_default();
+        let since = item.since.as_deref().map(|s| format!(", since {s}")).unwrap_or_default();
+        let deprecated =
+            item.deprecated.as_deref().map(|d| format!(", deprecated: {d}")).unwrap_or_default();
+        list.with_raw(format!(
+            "<li><code>{}</code> &mdash; {} ({}{}{}{}{})</li>",
+            html_escape(&item.path),
+            html_escape(&item.kind),
+            html_escape(&item.stability),
+            html_escape(&issue),
+            html_escape(&reason),
+            html_escape(&since),
+            html_escape(&deprecated),
+        ))
+    })
+}
+
+/// Renders every feature report as a single HTML page, with a collapsible `<details>` section
+/// per feature listing the items it gates.
+pub fn build_html_report(reports: &[FeatureReport]) -> String {
+    let body = reports.iter().fold(Container::new(ContainerType::Div), |body, report| {
+        body.with_raw(format!(
+            "<details><summary>{} ({} item{})</summary>{}</details>",
+            html_escape(&report.feature),
+            report.items.len(),
+            if report.items.len() == 1 { "" } else { "s" },
+            items_list(report).to_html_string(),
+        ))
+    });
+
+    HtmlPage::new()
+        .with_title("Unstable API report")
+        .with_header(1, "Unstable API report")
+        .with_container(body)
+        .to_html_string()
+}
@@ -1,4 +1,4 @@
-//! Dump the unstable API for a feature
+//! Dump the unstable API for one or more features.
 
 use std::{
     collections::HashMap,
@@ -7,135 +7,396 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use clap::{Parser, Subcommand, ValueEnum};
+use rayon::prelude::*;
 use rustdoc_types::{Crate, Id, Item};
-use syn::{parse::Parser, Ident, Lit, Meta, NestedMeta};
 
-fn is_ident(ident: &Ident, name: &str) -> bool {
-    *ident == Ident::new(name, ident.span())
+mod diff;
+mod html;
+mod report;
+mod stability;
+
+use stability::FeatureItem;
+
+/// Identifies an item by the crate it came from (its index into the `Vec<Crate>` returned by
+/// [`load_rustdoc_json_metadata`]) together with its rustdoc JSON `Id`.
+///
+/// A bare `Id` isn't enough: rustdoc JSON `Id`s are only unique *within* a single crate's
+/// document, so the same `Id` value is expected to collide across unrelated crates (e.g.
+/// `core.json` and `std.json`) when a whole directory is loaded at once.
+pub type GlobalId = (usize, Id);
+
+/// Dump or diff the surface area of the standard library gated behind unstable features.
+#[derive(Parser, Debug)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
 }
 
-/// Returns a `feature_name` -> Vec<`rustdoc_id`> items mapping.
-pub fn load_rustdoc_json_metadata(doc_dir: &Path) -> (Vec<Crate>, HashMap<String, Vec<Id>>) {
-    let mut all_items = HashMap::new();
-    let mut all_crates = vec![];
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Dump every item gated behind one or more unstable features.
+    Dump(DumpArgs),
+    /// Report how the unstable feature surface changed between two rustdoc JSON directories.
+    Diff(DiffArgs),
+}
 
-    for file in fs::read_dir(doc_dir).expect("failed to list files in directory") {
-        let entry = file.expect("failed to list file in directory");
-        let file = fs::File::open(entry.path()).expect("failed to open file");
-        let krate: Crate =
-            serde_json::from_reader(BufReader::new(file)).expect("failed to parse JSON docs");
+/// How to render the dumped item trees.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OutputFormat {
+    /// `{:#?}`-style debug output (the original behavior of this tool).
+    Debug,
+    /// Machine-readable JSON, suitable for diffing between toolchain builds.
+    Json,
+    /// A human-readable, indented listing of feature -> item paths.
+    Text,
+    /// A single browsable HTML page, grouping items by feature. Written to `--out`.
+    Html,
+}
 
-        let mut crate_items = HashMap::new();
-        for (id, item) in &krate.index {
-            if item.name.is_none() {
-                continue;
-            }
-            let unstable_feature = item.attrs.iter().find_map(|attr: &String| {
-                let Ok(parsed) = syn::Attribute::parse_outer.parse_str(attr).map(|mut v| v.swap_remove(0)) else {return None};
+#[derive(Parser, Debug)]
+struct DumpArgs {
+    /// Path to a directory of rustdoc JSON files (e.g. from `rustdoc --output-format json`).
+    doc_dir: PathBuf,
 
-                // Make sure this is an `unstable` attribute.
-                if !is_ident(parsed.path.get_ident()?, "unstable") {
-                    return None;
-                }
+    /// Feature names to dump (as they appear in `#[unstable(feature = "...")]`).
+    ///
+    /// Ignored if `--all` is passed.
+    #[arg(required_unless_present = "all")]
+    features: Vec<String>,
 
-                // Given `#[unstable(feature = "xyz")]`, return `(feature = "xyz")`.
-                let list = match parsed.parse_meta() {
-                    Ok(Meta::List(list)) => list,
-                    _ => return None,
-                };
-
-                // Given a `NestedMeta` like `feature = "xyz"`, returns `xyz`.
-                let get_feature_name = |nested: &_| {
-                    match nested {
-                        NestedMeta::Meta(Meta::NameValue(name_value)) => {
-                            if !is_ident(name_value.path.get_ident()?, "feature") {
-                                return None;
-                            }
-                            match &name_value.lit {
-                                Lit::Str(s) => Some(s.value()),
-                                _ => None,
-                            }
-                        }
-                        _ => None,
-                    }
-                };
+    /// Dump every unstable feature found in `doc_dir`, instead of just the ones named above.
+    #[arg(long)]
+    all: bool,
 
-                for nested in list.nested.iter() {
-                    if let Some(feat) = get_feature_name(nested) {
-                        return Some(feat);
-                    }
-                }
+    /// How to render the output.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Debug)]
+    format: OutputFormat,
 
-                None
-            });
-            if let Some(feat) = unstable_feature {
-                crate_items.insert(id, feat);
-            }
-        }
+    /// Where to write the report for `--format html`. Ignored by the other formats, which
+    /// always print to stdout.
+    #[arg(long, default_value = "report.html")]
+    out: PathBuf,
+}
+
+/// How to render a feature-surface diff.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum DiffFormat {
+    /// A human-readable, per-feature listing of added/removed/moved items.
+    Text,
+    /// Machine-readable JSON, one entry per feature.
+    Json,
+}
+
+#[derive(Parser, Debug)]
+struct DiffArgs {
+    /// Directory of rustdoc JSON files for the "before" snapshot.
+    old_doc_dir: PathBuf,
 
-        for (id, feat) in crate_items {
-            all_items.insert(id.clone(), feat);
+    /// Directory of rustdoc JSON files for the "after" snapshot.
+    new_doc_dir: PathBuf,
+
+    /// How to render the diff.
+    #[arg(long, value_enum, default_value_t = DiffFormat::Text)]
+    format: DiffFormat,
+}
+
+/// Parses a single rustdoc JSON file, returning the crate together with the stability metadata
+/// of every named item it defines that's gated behind an unstable or const-unstable feature.
+fn load_one_crate(path: &Path) -> (Crate, HashMap<Id, FeatureItem>) {
+    let file = fs::File::open(path).expect("failed to open file");
+    let krate: Crate =
+        serde_json::from_reader(BufReader::new(file)).expect("failed to parse JSON docs");
+
+    let mut crate_items = HashMap::new();
+    for (id, item) in &krate.index {
+        if item.name.is_none() {
+            continue;
+        }
+        if let Some(feature_item) = stability::stability_for_item(item) {
+            crate_items.insert(id.clone(), feature_item);
         }
+    }
+
+    (krate, crate_items)
+}
 
+/// Returns a `feature_name` -> Vec<[`GlobalId`]> items mapping, together with the stability
+/// metadata (tracking issue, `since`, deprecation, ...) recorded for each item.
+///
+/// Every file in `doc_dir` is parsed in parallel; the std library's doc set is large enough that
+/// sequential parsing is a significant chunk of this tool's runtime. Results are still combined
+/// via an order-preserving `collect`, so which crate ends up at which index doesn't depend on
+/// the order worker threads happen to finish in.
+pub fn load_rustdoc_json_metadata(
+    doc_dir: &Path,
+) -> (Vec<Crate>, HashMap<String, Vec<GlobalId>>, HashMap<GlobalId, FeatureItem>) {
+    let paths: Vec<PathBuf> = fs::read_dir(doc_dir)
+        .expect("failed to list files in directory")
+        .map(|file| file.expect("failed to list file in directory").path())
+        .collect();
+
+    let per_crate: Vec<(Crate, HashMap<Id, FeatureItem>)> =
+        paths.par_iter().map(|path| load_one_crate(path)).collect();
+
+    let mut all_crates = Vec::with_capacity(per_crate.len());
+    let mut per_crate_items = Vec::with_capacity(per_crate.len());
+    for (krate, crate_items) in per_crate {
         all_crates.push(krate);
+        per_crate_items.push(crate_items);
+    }
+    let (all_items, out) = merge_per_crate_items(per_crate_items);
+
+    (all_crates, out, all_items)
+}
+
+/// Merges the per-crate stability maps produced by [`load_one_crate`] into a single map keyed by
+/// [`GlobalId`], along with the `feature_name` -> Vec<[`GlobalId`]> mapping derived from it.
+///
+/// `per_crate_items[i]` is assumed to belong to the crate at index `i` in the `Vec<Crate>` this
+/// crate index is namespaced against; since a bare `Id` is only unique within its own crate's
+/// document, merging by `GlobalId` instead of `Id` means two crates reusing the same `Id` value
+/// (e.g. `core.json` and `std.json`) can never silently overwrite one another.
+fn merge_per_crate_items(
+    per_crate_items: Vec<HashMap<Id, FeatureItem>>,
+) -> (HashMap<GlobalId, FeatureItem>, HashMap<String, Vec<GlobalId>>) {
+    let mut all_items: HashMap<GlobalId, FeatureItem> = HashMap::new();
+    for (crate_idx, crate_items) in per_crate_items.into_iter().enumerate() {
+        for (id, feature_item) in crate_items {
+            all_items.insert((crate_idx, id), feature_item);
+        }
     }
 
     let mut out: HashMap<_, Vec<_>> = HashMap::new();
-    for (id, feature) in all_items {
-        out.entry(feature).or_default().push(id);
+    for (global_id, feature_item) in &all_items {
+        out.entry(feature_item.feature.clone()).or_default().push(global_id.clone());
     }
 
-    (all_crates, out)
+    (all_items, out)
 }
 
-fn extract_item_tree_for_id(crates: &[Crate], id: &Id) -> Vec<Vec<Id>> {
-    let path = crates.iter().flat_map(|c| c.paths.get(id)).collect::<Vec<_>>();
-    assert_eq!(path.len(), 1);
+/// Builds, for each prefix of `id`'s module path (within `krate` only), the id whose own path
+/// equals that prefix.
+///
+/// Returns `None` if any prefix can't be resolved to an item in `krate`'s `paths` map (e.g. an
+/// anonymous impl block with no path of its own). Where several items share a prefix's path,
+/// the first one found is used; which one that is is not defined.
+fn extract_item_tree_for_id(krate: &Crate, id: &Id) -> Option<Vec<Id>> {
+    let path = krate.paths.get(id)?;
     let mut built = Vec::new();
-    path[0]
-        .path
+    path.path
         .iter()
         .map(|seg| {
             built.push(seg.clone());
             built.clone()
         })
         .map(|path_segment| {
-            crates
-                .iter()
-                .flat_map(|c| {
-                    c.paths
-                        .iter()
-                        .find(|&(_, item)| &item.path == &path_segment)
-                        .map(|v| v.0.clone())
-                })
-                .collect::<Vec<_>>()
+            krate.paths.iter().find(|&(_, item)| item.path == path_segment).map(|(id, _)| id.clone())
         })
         .collect()
 }
 
-fn get_item_for_id(crates: &[Crate], id: &Id) -> Option<Item> {
-    for c in crates {
-        if let Some(item) = c.index.get(id) {
-            return Some(item.clone());
+/// Resolves every id in `ids` (all belonging to `krate`) to its full item, dropping any that can
+/// no longer be found.
+fn resolve_items(krate: &Crate, ids: &[Id]) -> Vec<Item> {
+    ids.iter().flat_map(|id| krate.index.get(id).cloned()).collect()
+}
+
+/// For each id in `feature_items`, resolves the chain of items from the crate root down to it
+/// (the id itself is kept alongside, so its stability metadata can be looked up later).
+///
+/// Items whose module path can't be fully resolved (see [`extract_item_tree_for_id`]) are
+/// skipped rather than causing the whole dump to panic; this is routine when `crates` spans
+/// several crates, e.g. a directory containing `core.json`, `alloc.json`, and `std.json`.
+fn item_trees_for_feature(crates: &[Crate], feature_items: &[GlobalId]) -> Vec<(GlobalId, Vec<Item>)> {
+    feature_items
+        .iter()
+        .filter_map(|&(crate_idx, ref id)| {
+            let krate = crates.get(crate_idx)?;
+            let chain = extract_item_tree_for_id(krate, id)?;
+            let tree = resolve_items(krate, &chain);
+            Some(((crate_idx, id.clone()), tree))
+        })
+        .collect()
+}
+
+fn dump_text(feature: &str, trees: &[(GlobalId, Vec<Item>)]) {
+    println!("{feature}:");
+    for (_, tree) in trees {
+        let path = tree.iter().map(|item| item.name.as_deref().unwrap_or("<unnamed>")).collect::<Vec<_>>().join("::");
+        println!("  {path}");
+    }
+}
+
+/// Loads `doc_dir` and builds a [`report::FeatureReport`] for every unstable feature found in it.
+fn all_feature_reports(doc_dir: &Path) -> Vec<report::FeatureReport> {
+    let (crates, mapping, stability) = load_rustdoc_json_metadata(doc_dir);
+    let trees_by_feature: Vec<(&str, Vec<(GlobalId, Vec<Item>)>)> = mapping
+        .iter()
+        .map(|(feature, ids)| (feature.as_str(), item_trees_for_feature(&crates, ids)))
+        .collect();
+    report::build_reports(
+        trees_by_feature.iter().map(|(feature, trees)| (*feature, trees.as_slice())),
+        &stability,
+    )
+}
+
+fn run_dump(args: DumpArgs) {
+    let (crates, mapping, stability) = load_rustdoc_json_metadata(&args.doc_dir);
+
+    let features: Vec<&String> = if args.all {
+        mapping.keys().collect()
+    } else {
+        args.features.iter().collect()
+    };
+
+    let trees_by_feature: Vec<(&str, Vec<(GlobalId, Vec<Item>)>)> = features
+        .iter()
+        .map(|feature| {
+            let items_from_feature = mapping.get(*feature).cloned().unwrap_or_default();
+            (feature.as_str(), item_trees_for_feature(&crates, &items_from_feature))
+        })
+        .collect();
+
+    match args.format {
+        OutputFormat::Debug => {
+            for (_, trees) in &trees_by_feature {
+                println!("{:#?}", trees.iter().map(|(_, tree)| tree).collect::<Vec<_>>());
+            }
+        }
+        OutputFormat::Json => {
+            let reports = report::build_reports(
+                trees_by_feature.iter().map(|(feature, trees)| (*feature, trees.as_slice())),
+                &stability,
+            );
+            serde_json::to_writer_pretty(std::io::stdout(), &reports)
+                .expect("failed to serialize feature report");
+            println!();
+        }
+        OutputFormat::Text => {
+            for (feature, trees) in &trees_by_feature {
+                dump_text(feature, trees);
+            }
+        }
+        OutputFormat::Html => {
+            let reports = report::build_reports(
+                trees_by_feature.iter().map(|(feature, trees)| (*feature, trees.as_slice())),
+                &stability,
+            );
+            fs::write(&args.out, html::build_html_report(&reports)).expect("failed to write HTML report");
+            eprintln!("wrote {}", args.out.display());
         }
     }
-    None
 }
 
-fn _get_item_name_for_id(crates: &[Crate], id: &Id) -> Vec<String> {
-    crates
-        .into_iter()
-        .flat_map(|c| c.paths.get(id).map(|s| s.path.clone()))
-        .next()
-        .unwrap_or_default()
+fn run_diff(args: DiffArgs) {
+    let old_reports = all_feature_reports(&args.old_doc_dir);
+    let new_reports = all_feature_reports(&args.new_doc_dir);
+    let deltas = diff::diff_reports(&old_reports, &new_reports);
+
+    match args.format {
+        DiffFormat::Text => {
+            for delta in &deltas {
+                if delta.changes.is_empty() {
+                    continue;
+                }
+                println!("{} ({:+})", delta.feature, delta.item_count_delta);
+                for change in &delta.changes {
+                    match &change.change {
+                        diff::Change::Added => println!("  + {}", change.path),
+                        diff::Change::Removed => println!("  - {}", change.path),
+                        diff::Change::MovedFrom(from) => {
+                            println!("  ~ {} (moved from {from})", change.path)
+                        }
+                        diff::Change::MovedTo(to) => {
+                            println!("  ~ {} (moved to {to})", change.path)
+                        }
+                    }
+                }
+            }
+        }
+        DiffFormat::Json => {
+            serde_json::to_writer_pretty(std::io::stdout(), &deltas)
+                .expect("failed to serialize feature diff");
+            println!();
+        }
+    }
 }
 
 fn main() {
-    let json_docs_path = PathBuf::from(std::env::args_os().nth(1).expect("Need path to json docs"));
-    let (crates, mapping) = load_rustdoc_json_metadata(&json_docs_path);
-    let items_from_feature = mapping.get("default_free_fn").cloned().unwrap_or(Vec::new());
-    let items = extract_item_tree_for_id(&crates, items_from_feature.first().unwrap())
-        .into_iter()
-        .map(|id| id.into_iter().flat_map(|id| get_item_for_id(&crates, &id)).next().unwrap()).collect::<Vec<_>>();
-    println!("{:#?}", items);
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Dump(args) => run_dump(args),
+        Command::Diff(args) => run_diff(args),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustdoc_types::Crate;
+
+    fn feature_item(feature: &str) -> FeatureItem {
+        FeatureItem {
+            feature: feature.to_owned(),
+            issue: None,
+            reason: None,
+            since: None,
+            stability: stability::Stability::Unstable,
+            deprecated: None,
+        }
+    }
+
+    fn empty_crate() -> Crate {
+        Crate {
+            root: Id(0),
+            crate_version: None,
+            includes_private: false,
+            index: HashMap::new(),
+            paths: HashMap::new(),
+            external_crates: HashMap::new(),
+            format_version: 0,
+        }
+    }
+
+    #[test]
+    fn merge_keeps_colliding_ids_from_different_crates_distinct() {
+        // `core.json` and `std.json` each mint their own `Id(0)`; namespacing by crate index is
+        // what keeps the second crate's item from silently overwriting the first's.
+        let mut core_items = HashMap::new();
+        core_items.insert(Id(0), feature_item("core_feature"));
+        let mut std_items = HashMap::new();
+        std_items.insert(Id(0), feature_item("std_feature"));
+
+        let (all_items, by_feature) = merge_per_crate_items(vec![core_items, std_items]);
+
+        assert_eq!(all_items.len(), 2);
+        assert_eq!(all_items[&(0, Id(0))].feature, "core_feature");
+        assert_eq!(all_items[&(1, Id(0))].feature, "std_feature");
+        assert_eq!(by_feature["core_feature"], vec![(0, Id(0))]);
+        assert_eq!(by_feature["std_feature"], vec![(1, Id(0))]);
+    }
+
+    #[test]
+    fn item_trees_for_feature_skips_ids_with_no_paths_entry() {
+        // Neither crate has a `paths` entry for this id, e.g. because it belongs to a crate
+        // that was never loaded, or the rustdoc JSON omitted it; this must be skipped rather
+        // than panicking.
+        let crates = vec![empty_crate(), empty_crate()];
+        let missing: GlobalId = (0, Id(42));
+
+        let trees = item_trees_for_feature(&crates, &[missing]);
+
+        assert!(trees.is_empty());
+    }
+
+    #[test]
+    fn item_trees_for_feature_skips_out_of_range_crate_index() {
+        let crates = vec![empty_crate()];
+        let out_of_range: GlobalId = (5, Id(1));
+
+        let trees = item_trees_for_feature(&crates, &[out_of_range]);
+
+        assert!(trees.is_empty());
+    }
 }
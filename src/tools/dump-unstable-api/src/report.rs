@@ -0,0 +1,164 @@
+//! A serializable form of the feature -> item mapping, for machine consumption.
+
+use std::collections::HashMap;
+
+use rustdoc_types::Item;
+use serde::Serialize;
+
+use crate::stability::FeatureItem;
+use crate::GlobalId;
+
+/// One item gated behind an unstable feature, along with the module tree leading to it.
+#[derive(Serialize)]
+pub struct ItemEntry {
+    /// Fully-qualified path, e.g. `core::intrinsics::default_free_fn`.
+    pub path: String,
+    /// The rustdoc item kind, e.g. `Function`, `Struct`, `Module`.
+    pub kind: String,
+    /// The tracking issue number, if recorded on the item's stability attribute.
+    pub issue: Option<String>,
+    /// The `reason = "..."` recorded on the item's `#[unstable(...)]` attribute, if any.
+    pub reason: Option<String>,
+    /// The `since = "..."` recorded on the item's `#[stable(...)]` attribute, if any.
+    pub since: Option<String>,
+    /// The deprecation note, if the item is `#[deprecated]`.
+    pub deprecated: Option<String>,
+    /// `Unstable`, `Stable`, or `StableConstUnstable` (see [`crate::stability::Stability`]).
+    pub stability: String,
+    /// The chain of items from the crate root down to (and including) this item.
+    pub tree: Vec<TreeNode>,
+}
+
+/// A single node in an item's module tree.
+#[derive(Serialize)]
+pub struct TreeNode {
+    pub name: String,
+    pub kind: String,
+}
+
+/// All the items gated behind a single unstable feature.
+#[derive(Serialize)]
+pub struct FeatureReport {
+    pub feature: String,
+    pub items: Vec<ItemEntry>,
+}
+
+/// Guesses a human-readable item kind from an item's `ItemEnum` debug representation.
+///
+/// Doesn't need to be exhaustive: this is only used for display purposes.
+fn item_kind(item: &Item) -> String {
+    let debug = format!("{:?}", item.inner);
+    debug.split(|c: char| !(c.is_alphanumeric() || c == '_')).next().unwrap_or_default().to_string()
+}
+
+/// Builds a [`FeatureReport`] for `feature`, given the chain of items (crate root to leaf, with
+/// the leaf's own id) for every item gated behind it.
+pub fn build_feature_report(
+    feature: &str,
+    item_trees: &[(GlobalId, Vec<Item>)],
+    stability: &HashMap<GlobalId, FeatureItem>,
+) -> FeatureReport {
+    let mut items: Vec<ItemEntry> = item_trees
+        .iter()
+        .filter_map(|(id, tree)| {
+            let leaf = tree.last()?;
+            let path = tree
+                .iter()
+                .map(|item| item.name.as_deref().unwrap_or("<unnamed>"))
+                .collect::<Vec<_>>()
+                .join("::");
+            let tree = tree
+                .iter()
+                .map(|item| TreeNode {
+                    name: item.name.clone().unwrap_or_default(),
+                    kind: item_kind(item),
+                })
+                .collect();
+            let meta = stability.get(id);
+            Some(ItemEntry {
+                path,
+                kind: item_kind(leaf),
+                issue: meta.and_then(|m| m.issue.clone()),
+                reason: meta.and_then(|m| m.reason.clone()),
+                since: meta.and_then(|m| m.since.clone()),
+                deprecated: meta.and_then(|m| m.deprecated.clone()),
+                stability: meta.map(|m| format!("{:?}", m.stability)).unwrap_or_default(),
+                tree,
+            })
+        })
+        .collect();
+    // `item_trees` is ultimately derived from hash map iteration upstream, so its order isn't
+    // stable across runs on byte-identical input; sort so the report is diffable.
+    items.sort_by(|a, b| a.path.cmp(&b.path));
+
+    FeatureReport { feature: feature.to_owned(), items }
+}
+
+/// Builds one report per feature, in a form ready to be serialized as a single JSON document.
+pub fn build_reports<'a>(
+    features: impl IntoIterator<Item = (&'a str, &'a [(GlobalId, Vec<Item>)])>,
+    stability: &HashMap<GlobalId, FeatureItem>,
+) -> Vec<FeatureReport> {
+    let mut reports: Vec<FeatureReport> = features
+        .into_iter()
+        .map(|(feature, trees)| build_feature_report(feature, trees, stability))
+        .collect();
+    // Same rationale as the per-item sort in `build_feature_report`: `features` is ultimately
+    // driven by hash map iteration upstream.
+    reports.sort_by(|a, b| a.feature.cmp(&b.feature));
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustdoc_types::{Id, ItemEnum, Module, Visibility};
+
+    /// Builds a tree (crate root to leaf) for `path`, one node per `::`-separated segment.
+    fn tree_for(global_id: GlobalId, path: &str) -> (GlobalId, Vec<Item>) {
+        (global_id, path.split("::").map(fake_item).collect())
+    }
+
+    fn fake_item(name: &str) -> Item {
+        Item {
+            id: Id(0),
+            crate_id: 0,
+            name: Some(name.to_owned()),
+            span: None,
+            visibility: Visibility::Public,
+            docs: None,
+            links: HashMap::new(),
+            attrs: Vec::new(),
+            deprecation: None,
+            inner: ItemEnum::Module(Module { is_crate: false, items: Vec::new(), is_stripped: false }),
+        }
+    }
+
+    #[test]
+    fn build_feature_report_sorts_items_by_path() {
+        let stability = HashMap::new();
+        // Deliberately out of order.
+        let trees = vec![tree_for((0, Id(0)), "a::c"), tree_for((0, Id(1)), "a::a"), tree_for((0, Id(2)), "a::b")];
+
+        let report = build_feature_report("foo", &trees, &stability);
+
+        let paths: Vec<&str> = report.items.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(paths, vec!["a::a", "a::b", "a::c"]);
+    }
+
+    #[test]
+    fn build_reports_sorts_by_feature() {
+        let stability = HashMap::new();
+        let zeta_trees = vec![tree_for((0, Id(0)), "z::a")];
+        let alpha_trees = vec![tree_for((0, Id(1)), "a::a")];
+
+        // Deliberately out of order: "zeta" listed before "alpha".
+        let reports = build_reports(
+            vec![("zeta", zeta_trees.as_slice()), ("alpha", alpha_trees.as_slice())],
+            &stability,
+        );
+
+        let features: Vec<&str> = reports.iter().map(|r| r.feature.as_str()).collect();
+        assert_eq!(features, vec!["alpha", "zeta"]);
+    }
+}
@@ -0,0 +1,194 @@
+//! Parses the stability attributes (`#[unstable]`, `#[rustc_const_unstable]`, `#[stable]`,
+//! `#[deprecated]`) rustdoc re-emits as plain attribute strings on every [`Item`].
+
+use std::collections::HashMap;
+
+use rustdoc_types::Item;
+use syn::{parse::Parser, Ident, Lit, Meta, NestedMeta};
+
+fn is_ident(ident: &Ident, name: &str) -> bool {
+    *ident == Ident::new(name, ident.span())
+}
+
+/// How an item's stability and its const-stability (if any) relate to each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stability {
+    /// Gated behind `#[unstable]` (or only behind `#[rustc_const_unstable]`, with no
+    /// `#[stable]` counterpart).
+    Unstable,
+    /// `#[stable]`, with no separate const-stability gate.
+    Stable,
+    /// `#[stable]` for normal use, but `#[rustc_const_unstable]` for use in `const` contexts.
+    StableConstUnstable,
+}
+
+/// Stability metadata for a single item, keyed under the unstable feature that gates it (its
+/// own `#[unstable(feature = "...")]`, or its `#[rustc_const_unstable(feature = "...")]` if it
+/// has no unstable feature of its own).
+#[derive(Debug, Clone)]
+pub struct FeatureItem {
+    pub feature: String,
+    pub issue: Option<String>,
+    pub reason: Option<String>,
+    pub since: Option<String>,
+    pub stability: Stability,
+    pub deprecated: Option<String>,
+}
+
+/// Parses a single attribute string (as found in [`Item::attrs`]) into its name-value pairs,
+/// if it's the attribute named `ident_name`.
+///
+/// Given `#[unstable(feature = "xyz", issue = "123")]` and `ident_name = "unstable"`, returns
+/// `{"feature": "xyz", "issue": "123"}`. Attributes with no parenthesized arguments (e.g. a
+/// bare `#[deprecated]`) return an empty map.
+fn attr_as_map(attr: &str, ident_name: &str) -> Option<HashMap<String, String>> {
+    let parsed = syn::Attribute::parse_outer.parse_str(attr).ok().map(|mut v| v.swap_remove(0))?;
+    if !is_ident(parsed.path.get_ident()?, ident_name) {
+        return None;
+    }
+
+    let mut map = HashMap::new();
+    match parsed.parse_meta() {
+        Ok(Meta::List(list)) => {
+            for nested in list.nested.iter() {
+                if let NestedMeta::Meta(Meta::NameValue(name_value)) = nested {
+                    if let (Some(key), Lit::Str(s)) = (name_value.path.get_ident(), &name_value.lit) {
+                        map.insert(key.to_string(), s.value());
+                    }
+                }
+            }
+        }
+        // A bare `#[deprecated]` with no arguments.
+        Ok(Meta::Path(_)) => {}
+        _ => return None,
+    }
+    Some(map)
+}
+
+/// Looks through `attrs` for the first attribute named `ident_name`, returning its parsed
+/// name-value pairs.
+fn find_attr<'a>(attrs: &'a [String], ident_name: &str) -> Option<HashMap<String, String>> {
+    attrs.iter().find_map(|attr| attr_as_map(attr, ident_name))
+}
+
+/// Builds the [`FeatureItem`] for an item's `attrs`, if it carries any unstable or const-unstable
+/// feature gate.
+///
+/// Items that are only `#[stable]` (with no separate const-stability gate) have nothing to
+/// report here and are skipped by the caller.
+pub fn stability_from_attrs(attrs: &[String]) -> Option<FeatureItem> {
+    let unstable = find_attr(attrs, "unstable");
+    let const_unstable = find_attr(attrs, "rustc_const_unstable");
+    let stable = find_attr(attrs, "stable");
+    let deprecated = find_attr(attrs, "deprecated");
+
+    let feature = unstable
+        .as_ref()
+        .and_then(|m| m.get("feature"))
+        .or_else(|| const_unstable.as_ref().and_then(|m| m.get("feature")))?
+        .clone();
+
+    let issue = unstable
+        .as_ref()
+        .and_then(|m| m.get("issue"))
+        .or_else(|| const_unstable.as_ref().and_then(|m| m.get("issue")))
+        .cloned();
+
+    let reason = unstable.as_ref().and_then(|m| m.get("reason")).cloned();
+
+    let since = stable.as_ref().and_then(|m| m.get("since")).cloned();
+
+    let stability = match (stable.is_some(), const_unstable.is_some()) {
+        (true, true) => Stability::StableConstUnstable,
+        (true, false) => Stability::Stable,
+        (false, _) => Stability::Unstable,
+    };
+
+    let deprecated = deprecated.map(|m| m.get("note").cloned().unwrap_or_default());
+
+    Some(FeatureItem { feature, issue, reason, since, stability, deprecated })
+}
+
+/// Builds the [`FeatureItem`] for `item`, if it carries any unstable or const-unstable feature
+/// gate. See [`stability_from_attrs`].
+pub fn stability_for_item(item: &Item) -> Option<FeatureItem> {
+    stability_from_attrs(&item.attrs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(strs: &[&str]) -> Vec<String> {
+        strs.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn plain_unstable() {
+        let item = stability_from_attrs(&attrs(&[
+            r#"#[unstable(feature = "foo_bar", issue = "12345", reason = "still cooking")]"#,
+        ]))
+        .unwrap();
+        assert_eq!(item.feature, "foo_bar");
+        assert_eq!(item.issue.as_deref(), Some("12345"));
+        assert_eq!(item.reason.as_deref(), Some("still cooking"));
+        assert_eq!(item.since, None);
+        assert_eq!(item.stability, Stability::Unstable);
+        assert_eq!(item.deprecated, None);
+    }
+
+    #[test]
+    fn const_unstable_only() {
+        let item = stability_from_attrs(&attrs(&[
+            r#"#[rustc_const_unstable(feature = "const_foo", issue = "999")]"#,
+        ]))
+        .unwrap();
+        assert_eq!(item.feature, "const_foo");
+        assert_eq!(item.issue.as_deref(), Some("999"));
+        assert_eq!(item.stability, Stability::Unstable);
+    }
+
+    #[test]
+    fn stable_with_no_const_gate() {
+        let item = stability_from_attrs(&attrs(&[r#"#[stable(since = "1.0.0")]"#]));
+        // A plain `#[stable]` item gates nothing, so there's no feature to key it under.
+        assert!(item.is_none());
+    }
+
+    #[test]
+    fn stable_but_const_unstable() {
+        let item = stability_from_attrs(&attrs(&[
+            r#"#[stable(since = "1.2.0")]"#,
+            r#"#[rustc_const_unstable(feature = "const_foo", issue = "42")]"#,
+        ]))
+        .unwrap();
+        assert_eq!(item.feature, "const_foo");
+        assert_eq!(item.since.as_deref(), Some("1.2.0"));
+        assert_eq!(item.stability, Stability::StableConstUnstable);
+    }
+
+    #[test]
+    fn deprecated_with_note() {
+        let item = stability_from_attrs(&attrs(&[
+            r#"#[unstable(feature = "foo_bar", issue = "1")]"#,
+            r#"#[deprecated(since = "1.0.0", note = "use `baz` instead")]"#,
+        ]))
+        .unwrap();
+        assert_eq!(item.deprecated.as_deref(), Some("use `baz` instead"));
+    }
+
+    #[test]
+    fn deprecated_without_note() {
+        let item = stability_from_attrs(&attrs(&[
+            r#"#[unstable(feature = "foo_bar", issue = "1")]"#,
+            r#"#[deprecated]"#,
+        ]))
+        .unwrap();
+        assert_eq!(item.deprecated.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn no_stability_attrs_at_all() {
+        assert!(stability_from_attrs(&attrs(&[r#"#[doc(hidden)]"#])).is_none());
+    }
+}